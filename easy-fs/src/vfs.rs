@@ -7,6 +7,10 @@ use super::{
     DIRENT_SZ,
     get_block_cache,
     block_cache_sync_all,
+    AccessMode,
+    ROOT_UID,
+    RenameFlags,
+    StatFs,
 };
 use alloc::sync::Arc;
 use alloc::string::String;
@@ -75,8 +79,11 @@ impl Inode {
         }
         None
     }
-    /// Find inode under current inode by name
-    pub fn find(&self, name: &str) -> Option<Arc<Inode>> {
+    /// Find inode under current inode by name; the caller needs EXEC on this directory
+    pub fn find(&self, uid: u32, gid: u32, name: &str) -> Option<Arc<Inode>> {
+        if !self.check_access(uid, gid, AccessMode::EXEC) {
+            return None;
+        }
         let fs = self.fs.lock();
         self.read_disk_inode(|disk_inode| {
             self.find_inode_id(name, disk_inode)
@@ -108,8 +115,12 @@ impl Inode {
         }
         disk_inode.increase_size(new_size, v, &self.block_device);
     }
-    /// Create inode under current inode by name
-    pub fn create(&self, name: &str) -> Option<Arc<Inode>> {
+    /// Create inode under current inode by name; the caller needs WRITE+EXEC
+    /// on this directory, and the new file is owned by (uid, gid)
+    pub fn create(&self, uid: u32, gid: u32, name: &str) -> Option<Arc<Inode>> {
+        if !self.check_access(uid, gid, AccessMode::WRITE | AccessMode::EXEC) {
+            return None;
+        }
         let mut fs = self.fs.lock();
         if self.modify_disk_inode(|root_inode| {
             // assert it is a directory
@@ -149,12 +160,14 @@ impl Inode {
         let (block_id, block_offset) = fs.get_disk_inode_pos(new_inode_id);
         block_cache_sync_all();
         // return inode
-        Some(Arc::new(Self::new(
+        let inode = Arc::new(Self::new(
             block_id,
             block_offset,
             self.fs.clone(),
             self.block_device.clone(),
-        )))
+        ));
+        inode.chown(uid, gid);
+        Some(inode)
         // release efs lock automatically by compiler
     }
     /// List inodes under current inode
@@ -178,22 +191,35 @@ impl Inode {
             v
         })
     }
-    /// Read data from current inode
-    pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+    /// Read data from current inode; the caller needs READ, None if denied
+    pub fn read_at(&self, uid: u32, gid: u32, offset: usize, buf: &mut [u8]) -> Option<usize> {
+        if !self.check_access(uid, gid, AccessMode::READ) {
+            return None;
+        }
         let _fs = self.fs.lock();
-        self.read_disk_inode(|disk_inode| {
+        Some(self.read_disk_inode(|disk_inode| {
             disk_inode.read_at(offset, buf, &self.block_device)
-        })
+        }))
     }
-    /// Write data to current inode
-    pub fn write_at(&self, offset: usize, buf: &[u8]) -> usize {
+    /// Write data to current inode; the caller needs WRITE, None if denied.
+    /// A write by a non-owner other than root clears the setuid/setgid bits.
+    pub fn write_at(&self, uid: u32, gid: u32, offset: usize, buf: &[u8]) -> Option<usize> {
+        if !self.check_access(uid, gid, AccessMode::WRITE) {
+            return None;
+        }
         let mut fs = self.fs.lock();
         let size = self.modify_disk_inode(|disk_inode| {
             self.increase_size((offset + buf.len()) as u32, disk_inode, &mut fs);
             disk_inode.write_at(offset, buf, &self.block_device)
         });
         block_cache_sync_all();
-        size
+        if uid != ROOT_UID {
+            let (file_uid, _) = self.get_owner();
+            if uid != file_uid {
+                self.modify_disk_inode(|disk_inode| disk_inode.clear_suid_sgid());
+            }
+        }
+        Some(size)
     }
     /// Clear the data in current inode
     pub fn clear(&self) {
@@ -310,14 +336,248 @@ impl Inode {
             if disknode.is_dir(){
                 0o040000
             }
+            else if disknode.is_symlink() {
+                0o120000
+            }
             else {
                 0o100000
             }
         })
     }
+    /// 在当前目录下创建一个符号链接，数据块中存放目标路径字符串；调用者需要对本目录拥有WRITE+EXEC权限，新链接归(uid,gid)所有
+    pub fn symlink(&self, uid: u32, gid: u32, linkname: &str, target: &str) -> Option<Arc<Inode>> {
+        if !self.check_access(uid, gid, AccessMode::WRITE | AccessMode::EXEC) {
+            return None;
+        }
+        let mut fs = self.fs.lock();
+        if self.modify_disk_inode(|root_inode| {
+            assert!(root_inode.is_dir(), "symlink parent is not a directory");
+            self.find_inode_id(linkname, root_inode)
+        }).is_some() {
+            return None;
+        }
+        let new_inode_id = fs.alloc_inode();
+        let (new_block_id, new_block_offset) = fs.get_disk_inode_pos(new_inode_id);
+        get_block_cache(new_block_id as usize, Arc::clone(&self.block_device))
+            .lock()
+            .modify(new_block_offset, |new_inode: &mut DiskInode| {
+                new_inode.initialize(DiskInodeType::SymLink);
+            });
+        let link_inode = Inode::new(
+            new_block_id,
+            new_block_offset,
+            self.fs.clone(),
+            self.block_device.clone(),
+        );
+        link_inode.modify_disk_inode(|link_disk_inode| {
+            self.increase_size(target.len() as u32, link_disk_inode, &mut fs);
+            link_disk_inode.write_at(0, target.as_bytes(), &self.block_device);
+        });
+        self.modify_disk_inode(|root_inode| {
+            let file_count = (root_inode.size as usize) / DIRENT_SZ;
+            let new_size = (file_count + 1) * DIRENT_SZ;
+            self.increase_size(new_size as u32, root_inode, &mut fs);
+            let dirent = DirEntry::new(linkname, new_inode_id);
+            root_inode.write_at(file_count * DIRENT_SZ, dirent.as_bytes(), &self.block_device);
+        });
+        block_cache_sync_all();
+        link_inode.chown(uid, gid);
+        Some(Arc::new(link_inode))
+    }
+    /// 读取符号链接的目标路径；目标可以不存在(悬空链接)，读取本身总是成功
+    pub fn read_link(&self) -> Option<String> {
+        self.read_disk_inode(|disk_inode| {
+            if !disk_inode.is_symlink() {
+                return None;
+            }
+            let mut buf = alloc::vec![0u8; disk_inode.size as usize];
+            disk_inode.read_at(0, &mut buf, &self.block_device);
+            Some(String::from_utf8(buf).unwrap())
+        })
+    }
+    /// 从当前目录开始解析路径，若路径的最后一级是符号链接就替换为其目标后继续，最多跳转
+    /// `MAX_SYMLINK_HOPS`次，超过则视为循环链接，返回None。
+    /// 只跟随最后一级的符号链接：中间某一级是符号链接时，find_path不会展开它，返回None
+    pub fn resolve(&self, path: &str) -> Option<Arc<Inode>> {
+        const MAX_SYMLINK_HOPS: usize = 40;
+        let mut remaining = String::from(path);
+        let mut hops = 0;
+        loop {
+            let found = self.find_path(&remaining)?;
+            let is_symlink = found.read_disk_inode(|d| d.is_symlink());
+            if !is_symlink {
+                return Some(found);
+            }
+            hops += 1;
+            if hops > MAX_SYMLINK_HOPS {
+                return None;
+            }
+            remaining = found.read_link()?;
+        }
+    }
+    /// 查看文件权限位
+    pub fn get_mode(&self) -> u32 {
+        self.read_disk_inode(|disk_inode| disk_inode.mode)
+    }
+    /// 设置文件权限位
+    pub fn set_mode(&self, mode: u32) {
+        self.modify_disk_inode(|disk_inode| {
+            disk_inode.mode = mode;
+        })
+    }
+    /// 查看文件属主/属组
+    pub fn get_owner(&self) -> (u32, u32) {
+        self.read_disk_inode(|disk_inode| (disk_inode.uid, disk_inode.gid))
+    }
+    /// 修改文件属主/属组
+    pub fn chown(&self, uid: u32, gid: u32) {
+        self.modify_disk_inode(|disk_inode| {
+            disk_inode.uid = uid;
+            disk_inode.gid = gid;
+        })
+    }
+    /// 判断(uid,gid)是否对本文件拥有want权限，mirrors Unix owner/group/other语义
+    pub fn check_access(&self, uid: u32, gid: u32, want: AccessMode) -> bool {
+        self.read_disk_inode(|disk_inode| {
+            DiskInode::check_access(uid, gid, disk_inode.uid, disk_inode.gid, disk_inode.mode, want)
+        })
+    }
+    /// 在当前目录下创建子目录，初始化好 "." 和 ".." 两个目录项；调用者需要对本目录拥有WRITE+EXEC权限，新目录归(uid,gid)所有
+    pub fn mkdir(&self, uid: u32, gid: u32, name: &str) -> Option<Arc<Inode>> {
+        if !self.check_access(uid, gid, AccessMode::WRITE | AccessMode::EXEC) {
+            return None;
+        }
+        let mut fs = self.fs.lock();
+        if self.modify_disk_inode(|parent_inode| {
+            assert!(parent_inode.is_dir(), "mkdir parent is not a directory");
+            self.find_inode_id(name, parent_inode)
+        }).is_some() {
+            return None;
+        }
+        let new_inode_id = fs.alloc_inode();
+        let (new_block_id, new_block_offset) = fs.get_disk_inode_pos(new_inode_id);
+        get_block_cache(new_block_id as usize, Arc::clone(&self.block_device))
+            .lock()
+            .modify(new_block_offset, |new_inode: &mut DiskInode| {
+                new_inode.initialize(DiskInodeType::Directory);
+            });
+        let new_dir = Arc::new(Self::new(
+            new_block_id,
+            new_block_offset,
+            self.fs.clone(),
+            self.block_device.clone(),
+        ));
+        // 写入 "." 指向自己，".." 指向父目录，并把父目录的 nlink 加一
+        let self_id = fs.get_disk_inode(self.block_id as u32, self.block_offset) as usize;
+        new_dir.modify_disk_inode(|dir_inode| {
+            let dot = DirEntry::new(".", new_inode_id);
+            let dotdot = DirEntry::new("..", self_id as u32);
+            new_dir.increase_size((2 * DIRENT_SZ) as u32, dir_inode, &mut fs);
+            dir_inode.write_at(0, dot.as_bytes(), &self.block_device);
+            dir_inode.write_at(DIRENT_SZ, dotdot.as_bytes(), &self.block_device);
+        });
+        new_dir.add_disk_nlink();
+        self.modify_disk_inode(|disk_inode| disk_inode.nlink += 1);
+        // 把新目录链接到父目录的目录项表中
+        self.modify_disk_inode(|parent_inode| {
+            let file_count = (parent_inode.size as usize) / DIRENT_SZ;
+            let new_size = (file_count + 1) * DIRENT_SZ;
+            self.increase_size(new_size as u32, parent_inode, &mut fs);
+            let dirent = DirEntry::new(name, new_inode_id);
+            parent_inode.write_at(file_count * DIRENT_SZ, dirent.as_bytes(), &self.block_device);
+        });
+        block_cache_sync_all();
+        new_dir.chown(uid, gid);
+        Some(new_dir)
+    }
+    /// 按 '/' 切分路径，从当前目录开始逐级解析，找不到则返回None
+    pub fn find_path(&self, path: &str) -> Option<Arc<Inode>> {
+        let fs = self.fs.lock();
+        let mut block_id = self.block_id as u32;
+        let mut block_offset = self.block_offset;
+        for component in path.split('/').filter(|s| !s.is_empty()) {
+            let cur = Self::new(block_id, block_offset, self.fs.clone(), self.block_device.clone());
+            let inode_id = cur.read_disk_inode(|disk_inode| {
+                if !disk_inode.is_dir() {
+                    return None;
+                }
+                cur.find_inode_id(component, disk_inode)
+            })?;
+            let pos = fs.get_disk_inode_pos(inode_id);
+            block_id = pos.0 as u32;
+            block_offset = pos.1;
+        }
+        Some(Arc::new(Self::new(block_id, block_offset, self.fs.clone(), self.block_device.clone())))
+    }
+    /// 原子重命名目录项，flags控制NOREPLACE/EXCHANGE语义，写回后立即持久化
+    pub fn rename(&self, old_name: &str, new_name: &str, flags: RenameFlags) -> isize {
+        let _fs = self.fs.lock();
+        let result = self.modify_disk_inode(|root_inode| {
+            assert!(root_inode.is_dir(), "rename parent is not a directory");
+            let file_count = (root_inode.size as usize) / DIRENT_SZ;
+            let mut old_idx = None;
+            let mut new_idx = None;
+            for i in 0..file_count {
+                let mut dirent = DirEntry::empty();
+                root_inode.read_at(i * DIRENT_SZ, dirent.as_bytes_mut(), &self.block_device);
+                if dirent.name() == old_name {
+                    old_idx = Some(i);
+                }
+                if dirent.name() == new_name {
+                    new_idx = Some(i);
+                }
+            }
+            let old_idx = match old_idx {
+                Some(i) => i,
+                None => return -1,
+            };
+            let mut old_entry = DirEntry::empty();
+            root_inode.read_at(old_idx * DIRENT_SZ, old_entry.as_bytes_mut(), &self.block_device);
+
+            if flags.contains(RenameFlags::EXCHANGE) {
+                let new_idx = match new_idx {
+                    Some(i) => i,
+                    None => return -1,
+                };
+                let mut new_entry = DirEntry::empty();
+                root_inode.read_at(new_idx * DIRENT_SZ, new_entry.as_bytes_mut(), &self.block_device);
+                let swapped_old = DirEntry::new(old_name, new_entry.inode_number());
+                let swapped_new = DirEntry::new(new_name, old_entry.inode_number());
+                root_inode.write_at(old_idx * DIRENT_SZ, swapped_old.as_bytes(), &self.block_device);
+                root_inode.write_at(new_idx * DIRENT_SZ, swapped_new.as_bytes(), &self.block_device);
+                return 0;
+            }
+
+            if new_idx.is_some() && flags.contains(RenameFlags::NOREPLACE) {
+                return -1;
+            }
+
+            if new_idx == Some(old_idx) {
+                // renaming a name to itself: nothing to move
+                return 0;
+            }
+            let renamed = DirEntry::new(new_name, old_entry.inode_number());
+            if let Some(new_idx) = new_idx {
+                // 先覆盖已存在的new_name目录项，再清空旧的old_name目录项
+                root_inode.write_at(new_idx * DIRENT_SZ, renamed.as_bytes(), &self.block_device);
+                root_inode.write_at(old_idx * DIRENT_SZ, DirEntry::empty().as_bytes(), &self.block_device);
+            } else {
+                root_inode.write_at(old_idx * DIRENT_SZ, renamed.as_bytes(), &self.block_device);
+            }
+            0
+        });
+        block_cache_sync_all();
+        result
+    }
+    /// 查看所在文件系统的容量使用情况
+    pub fn stat_fs(&self) -> StatFs {
+        let fs = self.fs.lock();
+        fs.stat_fs()
+    }
     pub fn delete_file(&self,path:&str)->isize{
         //删除文件
         self.modify_disk_inode(|root_inode| {
+            assert!(root_inode.is_dir(), "delete_file parent is not a directory");
             // append file in the dirent
             let file_count = (root_inode.size as usize) / DIRENT_SZ;
             // 找到对应的目录项
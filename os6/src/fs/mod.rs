@@ -0,0 +1,104 @@
+//! Kernel-side file handles, layered on top of the `easy-fs` vfs
+//!
+//! `OSInode` is what `open_file` hands back to syscalls: an `easy_fs::Inode`
+//! plus the read/write cursor a file descriptor needs but the vfs itself
+//! doesn't track.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use easy_fs::{EasyFileSystem, Inode, StatFs};
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::drivers::BLOCK_DEVICE;
+
+bitflags! {
+    pub struct OpenFlags: u32 {
+        const RDONLY = 0;
+        const WRONLY = 1 << 0;
+        const RDWR = 1 << 1;
+    }
+}
+
+impl OpenFlags {
+    /// Translate the flag bits into (readable, writable)
+    pub fn read_write(&self) -> (bool, bool) {
+        if self.contains(Self::WRONLY) {
+            (false, true)
+        } else if self.contains(Self::RDWR) {
+            (true, true)
+        } else {
+            (true, false)
+        }
+    }
+}
+
+lazy_static! {
+    static ref ROOT_INODE: Arc<Inode> = {
+        let fs = EasyFileSystem::open(BLOCK_DEVICE.clone());
+        let (block_id, block_offset) = fs.lock().get_disk_inode_pos(0);
+        Arc::new(Inode::new(block_id, block_offset, fs, BLOCK_DEVICE.clone()))
+    };
+}
+
+struct OSInodeInner {
+    offset: usize,
+    inode: Arc<Inode>,
+}
+
+/// A file descriptor's view onto an `easy_fs::Inode`: the identity it was
+/// opened under, whether it is writable, plus the cursor position that the
+/// vfs layer doesn't keep.
+pub struct OSInode {
+    uid: u32,
+    gid: u32,
+    writable: bool,
+    inner: Mutex<OSInodeInner>,
+}
+
+impl OSInode {
+    pub fn new(uid: u32, gid: u32, writable: bool, inode: Arc<Inode>) -> Self {
+        Self {
+            uid,
+            gid,
+            writable,
+            inner: Mutex::new(OSInodeInner { offset: 0, inode }),
+        }
+    }
+
+    /// Read the whole file into a freshly allocated buffer, advancing the
+    /// cursor. `None` means permission was denied partway through (or on the
+    /// very first read) rather than the file being empty.
+    pub fn read_all(&self) -> Option<Vec<u8>> {
+        let mut inner = self.inner.lock();
+        let mut buffer = [0u8; 512];
+        let mut data = Vec::new();
+        loop {
+            match inner.inode.read_at(self.uid, self.gid, inner.offset, &mut buffer) {
+                None => return None,
+                Some(0) => break,
+                Some(len) => {
+                    inner.offset += len;
+                    data.extend_from_slice(&buffer[..len]);
+                }
+            }
+        }
+        Some(data)
+    }
+
+    /// Forward to the underlying inode's filesystem usage summary
+    pub fn stat_fs(&self) -> StatFs {
+        self.inner.lock().inode.stat_fs()
+    }
+}
+
+/// Open a file by name in the filesystem root directory, checked against the
+/// given identity (the caller's task uid/gid — see `TaskControlBlockInner::uid`).
+/// `name` is a single directory entry; '/'-separated paths are not walked.
+pub fn open_file(uid: u32, gid: u32, name: &str, flags: OpenFlags) -> Option<Arc<OSInode>> {
+    let (_, writable) = flags.read_write();
+    ROOT_INODE
+        .find(uid, gid, name)
+        .map(|inode| Arc::new(OSInode::new(uid, gid, writable, inode)))
+}
@@ -0,0 +1,40 @@
+//! File system related syscalls
+
+use crate::mm::translated_refmut;
+use crate::task::{current_task, current_user_token};
+use crate::fs::open_file;
+
+/// Mirrors `easy_fs::StatFs`, laid out for a user-space `struct statfs`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatFs {
+    pub block_size: u32,
+    pub total_blocks: u32,
+    pub free_blocks: u32,
+    pub total_inodes: u32,
+    pub free_inodes: u32,
+}
+
+/// Report how full the filesystem backing `path` is
+pub fn sys_statfs(path: *const u8, buf: *mut StatFs) -> isize {
+    let token = current_user_token();
+    let path = crate::mm::translated_str(token, path);
+    let (uid, gid) = {
+        let task = current_task().unwrap();
+        let inner = task.inner_exclusive_access();
+        (inner.uid, inner.gid)
+    };
+    if let Some(inode) = open_file(uid, gid, path.as_str(), crate::fs::OpenFlags::RDONLY) {
+        let stat = inode.stat_fs();
+        *translated_refmut(token, buf) = StatFs {
+            block_size: stat.block_size,
+            total_blocks: stat.total_blocks,
+            free_blocks: stat.free_blocks,
+            total_inodes: stat.total_inodes,
+            free_inodes: stat.free_inodes,
+        };
+        0
+    } else {
+        -1
+    }
+}
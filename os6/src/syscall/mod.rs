@@ -0,0 +1,53 @@
+//! Implementation of syscalls
+//!
+//! The single entry point to all system calls, `syscall()`, is called
+//! whenever `ecall` is executed in a user program. `syscall` also records,
+//! on the current task, how many times each syscall id has been invoked so
+//! that `sys_task_info` can report it back to user space.
+
+const SYSCALL_EXIT: usize = 93;
+const SYSCALL_YIELD: usize = 124;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_GETPID: usize = 172;
+const SYSCALL_FORK: usize = 220;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_SPAWN: usize = 400;
+const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_STATFS: usize = 420;
+
+mod fs;
+mod process;
+
+use fs::*;
+use process::*;
+use crate::task::current_task;
+
+/// Handle a syscall trapped from user space, dispatching by `syscall_id`.
+pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
+    if let Some(task) = current_task() {
+        let mut inner = task.inner_exclusive_access();
+        if syscall_id < inner.syscall_times.len() {
+            inner.syscall_times[syscall_id] += 1;
+        }
+    }
+    match syscall_id {
+        SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_YIELD => sys_yield(),
+        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
+        SYSCALL_GETPID => sys_getpid(),
+        SYSCALL_FORK => sys_fork(),
+        SYSCALL_EXEC => sys_exec(args[0] as *const u8),
+        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2]),
+        SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
+        SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
+        SYSCALL_STATFS => sys_statfs(args[0] as *const u8, args[1] as *mut StatFs),
+        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+    }
+}
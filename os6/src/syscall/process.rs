@@ -58,11 +58,19 @@ pub fn sys_fork() -> isize {
 pub fn sys_exec(path: *const u8) -> isize {
     let token = current_user_token();
     let path = translated_str(token, path);
-    if let Some(app_inode) = open_file(path.as_str(), OpenFlags::RDONLY) {
-        let all_data = app_inode.read_all();
-        let task = current_task().unwrap();
-        task.exec(all_data.as_slice());
-        0
+    let task = current_task().unwrap();
+    let (uid, gid) = {
+        let inner = task.inner_exclusive_access();
+        (inner.uid, inner.gid)
+    };
+    if let Some(app_inode) = open_file(uid, gid, path.as_str(), OpenFlags::RDONLY) {
+        match app_inode.read_all() {
+            Some(all_data) => {
+                task.exec(all_data.as_slice());
+                0
+            }
+            None => -1,
+        }
     } else {
         -1
     }
@@ -124,12 +132,25 @@ pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize {
 
 // YOUR JOB: 引入虚地址后重写 sys_task_info
 pub fn sys_task_info(ti: *mut TaskInfo) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let now_us = get_time_us();
+    let info = TaskInfo {
+        status: TaskStatus::Running,
+        syscall_times: inner.syscall_times,
+        time: inner.time_since_first_run_ms(now_us),
+    };
+    drop(inner);
+    let token = current_user_token();
+    *translated_refmut(token, ti) = info;
     0
 }
 
 // YOUR JOB: 实现sys_set_priority，为任务添加优先级
-pub fn sys_set_priority(_prio: isize) -> isize {
-    -1
+pub fn sys_set_priority(prio: isize) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner.set_priority(prio)
 }
 
 // YOUR JOB: 扩展内核以实现 sys_mmap 和 sys_munmap
@@ -188,10 +209,15 @@ pub fn sys_spawn(path: *const u8) -> isize {
     let token = current_user_token();
     let name = translated_str(token,path);//查找是否存在此应用程序
     let task = current_task().unwrap();
-    if let Some(app_inode) = open_file(name.as_str(), OpenFlags::RDONLY) {
-        let all_data = app_inode.read_all();
-        let task = current_task().unwrap();
-        task.spawn(all_data.as_slice())
+    let (uid, gid) = {
+        let inner = task.inner_exclusive_access();
+        (inner.uid, inner.gid)
+    };
+    if let Some(app_inode) = open_file(uid, gid, name.as_str(), OpenFlags::RDONLY) {
+        match app_inode.read_all() {
+            Some(all_data) => task.spawn(all_data.as_slice()),
+            None => -1,
+        }
     } else {
         -1
     }
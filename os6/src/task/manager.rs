@@ -0,0 +1,65 @@
+//! Implementation of [`TaskManager`]
+//!
+//! The task ready queue used to be a plain FIFO. It is now a min-selection
+//! over each task's stride so that `fetch` always hands the scheduler the
+//! task with the smallest accumulated stride (see `stride_less` in `task.rs`
+//! for the overflow-safe comparison).
+
+use super::task::{stride_less, TaskControlBlock};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use lazy_static::*;
+use crate::sync::UPSafeCell;
+use crate::timer::get_time_us;
+
+pub struct TaskManager {
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+/// A simple FIFO scheduler would just push_back/pop_front; the stride
+/// scheduler instead does a linear scan for the minimum stride on `fetch`,
+/// which is cheap given the small number of runnable tasks in this kernel.
+impl TaskManager {
+    pub fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+        }
+    }
+    pub fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push_back(task);
+    }
+    pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        let mut min_idx = None;
+        for (idx, task) in self.ready_queue.iter().enumerate() {
+            let stride = task.inner_exclusive_access().stride;
+            let is_new_min = match min_idx {
+                None => true,
+                Some(cur) => {
+                    let cur_stride: usize =
+                        self.ready_queue[cur].inner_exclusive_access().stride;
+                    stride_less(stride, cur_stride)
+                }
+            };
+            if is_new_min {
+                min_idx = Some(idx);
+            }
+        }
+        min_idx.and_then(|idx| self.ready_queue.remove(idx)).map(|task| {
+            task.inner_exclusive_access().on_dispatch(get_time_us());
+            task
+        })
+    }
+}
+
+lazy_static! {
+    pub static ref TASK_MANAGER: UPSafeCell<TaskManager> =
+        unsafe { UPSafeCell::new(TaskManager::new()) };
+}
+
+pub fn add_task(task: Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().add(task);
+}
+
+pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().fetch()
+}
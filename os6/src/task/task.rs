@@ -0,0 +1,125 @@
+//! Types related to task management
+
+use super::TaskContext;
+use crate::config::{MAX_SYSCALL_NUM, TRAP_CONTEXT};
+use crate::mm::{MemorySet, PhysPageNum, VirtAddr, KERNEL_SPACE, MapPermission};
+use crate::trap::{trap_handler, TrapContext};
+use crate::sync::UPSafeCell;
+use super::pid::{pid_alloc, KernelStack, PidHandle};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cell::RefMut;
+
+/// Numerator of every task's pass (`pass = BIG_STRIDE / priority`): a task's
+/// stride grows by BIG_STRIDE over `priority` dispatches, so CPU share is
+/// proportional to priority.
+pub const BIG_STRIDE: usize = 0x10000;
+
+pub struct TaskControlBlock {
+    // immutable
+    pub pid: PidHandle,
+    pub kernel_stack: KernelStack,
+    // mutable
+    inner: UPSafeCell<TaskControlBlockInner>,
+}
+
+pub struct TaskControlBlockInner {
+    pub trap_cx_ppn: PhysPageNum,
+    pub base_size: usize,
+    pub task_cx: TaskContext,
+    pub task_status: TaskStatus,
+    pub memory_set: MemorySet,
+    pub parent: Option<Weak<TaskControlBlock>>,
+    pub children: Vec<Arc<TaskControlBlock>>,
+    pub exit_code: i32,
+    pub heap_bottom: usize,
+    pub program_brk: usize,
+    /// Number of times each syscall has been invoked by this task, indexed by syscall id
+    pub syscall_times: [u32; MAX_SYSCALL_NUM],
+    /// Timestamp (in us, from `get_time_us`) of the first time this task was dispatched
+    pub first_run_time: Option<usize>,
+    /// Scheduling priority, minimum 2, default 16
+    pub priority: isize,
+    /// Current stride accumulated by the stride scheduler
+    pub stride: usize,
+    /// Stride increment applied each time this task is scheduled: BIG_STRIDE / priority
+    pub pass: usize,
+    /// Owning user id, checked against `easy_fs::Inode::check_access` on open.
+    /// Defaults to `ROOT_UID`; task creation is responsible for dropping this
+    /// to a real identity where one is known.
+    pub uid: u32,
+    /// Owning group id, see `uid`.
+    pub gid: u32,
+}
+
+impl TaskControlBlockInner {
+    pub fn get_trap_cx(&self) -> &'static mut TrapContext {
+        self.trap_cx_ppn.get_mut()
+    }
+    pub fn get_user_token(&self) -> usize {
+        self.memory_set.token()
+    }
+    fn get_status(&self) -> TaskStatus {
+        self.task_status
+    }
+    pub fn is_zombie(&self) -> bool {
+        self.get_status() == TaskStatus::Zombie
+    }
+
+    /// Record that this task has just been picked by the scheduler: bump its
+    /// stride by `pass` and, the first time this happens, stamp `first_run_time`.
+    pub fn on_dispatch(&mut self, now_us: usize) {
+        if self.first_run_time.is_none() {
+            self.first_run_time = Some(now_us);
+        }
+        self.stride = self.stride.wrapping_add(self.pass);
+    }
+
+    /// Elapsed milliseconds since this task was first dispatched, 0 if it
+    /// has not run yet. Relies on `TaskManager::fetch` calling `on_dispatch`
+    /// to stamp `first_run_time` the first time this task is scheduled.
+    pub fn time_since_first_run_ms(&self, now_us: usize) -> usize {
+        match self.first_run_time {
+            Some(start) => (now_us - start) / 1000,
+            None => 0,
+        }
+    }
+
+    /// Set the task priority, enforcing `2 <= prio <= BIG_STRIDE`, and recompute `pass`.
+    ///
+    /// The upper bound keeps `pass = BIG_STRIDE / prio` at least 1: a `pass`
+    /// of 0 would never advance the task's stride, so it would win every
+    /// `fetch()` forever and starve the rest of the ready queue.
+    pub fn set_priority(&mut self, prio: isize) -> isize {
+        if prio < 2 || prio > BIG_STRIDE as isize {
+            return -1;
+        }
+        self.priority = prio;
+        self.pass = BIG_STRIDE / self.priority as usize;
+        prio
+    }
+}
+
+impl TaskControlBlock {
+    pub fn inner_exclusive_access(&self) -> RefMut<'_, TaskControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+
+    pub fn getpid(&self) -> usize {
+        self.pid.0
+    }
+}
+
+/// Compare two strides using wrapping/modular distance so that an overflowed
+/// stride still sorts correctly relative to the others: `a` precedes `b` iff
+/// the forward distance from `a` to `b` is within half the stride space.
+pub fn stride_less(a: usize, b: usize) -> bool {
+    (b.wrapping_sub(a) as isize) > 0 && b.wrapping_sub(a) <= BIG_STRIDE / 2
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum TaskStatus {
+    Ready,
+    Running,
+    Zombie,
+}